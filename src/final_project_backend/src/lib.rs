@@ -2,7 +2,7 @@
 use candid::{CandidType, Decode, Deserialize, Encode}; // Enables the availability of external libraries
 use ic_stable_structures::memory_manager::{MemoryId, MemoryManager, VirtualMemory}; // Contains necessary structures for memory management
 use ic_stable_structures::{BoundedStorable, DefaultMemoryImpl, StableBTreeMap, Storable}; // Contains structures for storage operations
-use std::{borrow::Cow, cell::RefCell}; // Includes certain structures from the standard library
+use std::{borrow::Cow, cell::RefCell, collections::BTreeSet}; // Includes certain structures from the standard library
 
 // Defines the type of virtual memory
 type Memory = VirtualMemory<DefaultMemoryImpl>;
@@ -10,8 +10,27 @@ type Memory = VirtualMemory<DefaultMemoryImpl>;
 // Defines a constant value specifying the maximum size of Proposal values
 const MAX_VALUE_SIZE: u32 = 5000;
 
+// Defines the length of the closing-period extension window, in nanoseconds (1 hour)
+const CLOSING_PERIOD_NS: u64 = 3_600_000_000_000;
+
+// Defines the length of the grant period between a Proposal's creation and its voting window
+// opening, in nanoseconds (1 hour), during which the chairperson sets up the electorate
+const GRANT_PERIOD_NS: u64 = 3_600_000_000_000;
+
+// Defines a cap on how many links a delegation chain may be followed before giving up
+const MAX_DELEGATION_DEPTH: u32 = 32;
+
+// Defines a constant value specifying the maximum size of Event values
+const MAX_EVENT_SIZE: u32 = 200;
+
+// Defines a constant value specifying the maximum size of GovernanceConfig values
+const MAX_GOVERNANCE_CONFIG_SIZE: u32 = 50;
+
+// Defines the key under which the singleton GovernanceConfig and admin principal are stored
+const SINGLETON_KEY: u8 = 0;
+
 // Defines an enum representing choices users can make
-#[derive(Debug, CandidType, Deserialize)]
+#[derive(Debug, Clone, Copy, CandidType, Deserialize)]
 enum Choice {
     Approve,
     Reject,
@@ -26,11 +45,47 @@ enum VoteError {
     NoSuchProposal,
     AccessRejected,
     UpdateError,
+    InvalidDelegation,
+    DurationTooShort,
+    NotSucceeded,
+    ProposalStillOpen,
+}
+
+// Defines an enum representing the lifecycle state of a Proposal
+#[derive(Debug, Clone, PartialEq, CandidType, Deserialize)]
+enum ProposalState {
+    Pending,  // Voting window has not opened yet
+    Active,   // Voting window is currently open
+    Defeated, // Voting window closed below quorum, or approve failed to exceed the approval
+    // threshold — with a threshold of 50% or more this includes an approve == reject tie
+    Succeeded, // Voting window closed with approve exceeding the approval threshold of participating weight
+    Expired,   // Voting window closed with no participating weight at all
+    Executed,  // Proposal outcome has been carried out
+}
+
+// Defines an enum representing a governance event recorded in the audit trail
+#[derive(Debug, Clone, CandidType, Deserialize)]
+enum Event {
+    ProposalCreated {
+        key: u64,
+        owner: candid::Principal,
+    },
+    VoteCast {
+        key: u64,
+        voter: candid::Principal,
+        choice: Choice,
+        weight: u32,
+    },
+    StateChanged {
+        key: u64,
+        new_state: ProposalState,
+    },
 }
 
-// Defines a struct representing a Proposal
+// Defines a struct representing the original Proposal layout, before lifecycle timestamps,
+// weights and the state machine existed
 #[derive(Debug, CandidType, Deserialize)]
-struct Proposal {
+struct ProposalV1 {
     description: String,           // Description field of the Proposal
     approve: u32,                  // Approval count
     reject: u32,                   // Rejection count
@@ -40,15 +95,117 @@ struct Proposal {
     owner: candid::Principal,      // Owner of the Proposal
 }
 
+// Defines a struct representing the current Proposal layout
+#[derive(Debug, Clone, CandidType, Deserialize)]
+struct ProposalV2 {
+    description: String,           // Description field of the Proposal
+    approve: u32,                  // Approval count
+    reject: u32,                   // Rejection count
+    pass: u32,                     // Pass count
+    state: ProposalState,          // Field tracking the lifecycle state of the Proposal
+    start_time_ns: u64,            // Timestamp at which the voting window opens
+    end_time_ns: u64,              // Timestamp at which the voting window closes
+    extended: bool,                // Whether the closing-period extension has already been used
+    voted: Vec<candid::Principal>, // List of users who voted
+    owner: candid::Principal,      // Owner of the Proposal
+}
+
+// Implements upcasting from the original layout, defaulting the fields it never had
+impl From<ProposalV1> for ProposalV2 {
+    fn from(v1: ProposalV1) -> Self {
+        ProposalV2 {
+            description: v1.description,
+            approve: v1.approve,
+            reject: v1.reject,
+            pass: v1.pass,
+            state: if v1.is_active {
+                ProposalState::Active
+            } else {
+                ProposalState::Defeated
+            },
+            start_time_ns: 0,
+            end_time_ns: 0,
+            extended: false,
+            voted: v1.voted,
+            owner: v1.owner,
+        }
+    }
+}
+
+// Defines the versioned encoding actually persisted in stable memory, so that field additions
+// to the Proposal layout no longer break decoding of entries written by an older canister build
+#[derive(Debug, CandidType, Deserialize)]
+enum VersionedProposal {
+    V1(ProposalV1),
+    V2(ProposalV2),
+}
+
+impl VersionedProposal {
+    // Upcasts any stored version to the current Proposal layout
+    fn into_v2(self) -> ProposalV2 {
+        match self {
+            VersionedProposal::V1(v1) => v1.into(),
+            VersionedProposal::V2(v2) => v2,
+        }
+    }
+}
+
 // Defines a struct containing necessary information to create a new Proposal
 #[derive(Debug, CandidType, Deserialize)]
 struct CreateProposal {
     description: String, // Description field of the Proposal
-    is_active: bool,     // Field indicating if the Proposal is active
+    duration_ns: u64, // Length of the voting window, in nanoseconds, starting after GRANT_PERIOD_NS
+}
+
+// Defines a struct holding the governance parameters that gate proposal outcomes
+#[derive(Debug, Clone, CandidType, Deserialize)]
+struct GovernanceConfig {
+    min_duration_ns: u64, // Shortest voting window a Proposal may be created with
+    quorum_weight: u32,   // Minimum participating weight required for a Proposal to pass
+    approval_threshold_pct: u8, // Share of participating weight approve must exceed to pass
+}
+
+// Implements a conservative default for GovernanceConfig: no minimum duration or quorum, simple majority
+impl Default for GovernanceConfig {
+    fn default() -> Self {
+        GovernanceConfig {
+            min_duration_ns: 0,
+            quorum_weight: 0,
+            approval_threshold_pct: 50,
+        }
+    }
 }
 
 // Implements traits for storable data types
-impl Storable for Proposal {
+impl Storable for VersionedProposal {
+    // Function to convert data to bytes
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    // Function to convert bytes to data, falling back through older encodings so that a field
+    // addition never breaks decoding of entries written by a previous canister build
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        if let Ok(versioned) = Decode!(bytes.as_ref(), Self) {
+            return versioned;
+        }
+        // Falls back to the raw (pre-versioning) encoding of the current layout
+        if let Ok(v2) = Decode!(bytes.as_ref(), ProposalV2) {
+            return VersionedProposal::V2(v2);
+        }
+        // Falls back further to the raw encoding of the original layout
+        VersionedProposal::V1(Decode!(bytes.as_ref(), ProposalV1).unwrap())
+    }
+}
+
+// Implements traits for bounded storable data types
+impl BoundedStorable for VersionedProposal {
+    const MAX_SIZE: u32 = MAX_VALUE_SIZE; // Maximum size
+    const IS_FIXED_SIZE: bool = false; // Whether it's a fixed size or not
+}
+
+// Implements traits for storable data types
+impl Storable for Event {
     // Function to convert data to bytes
     fn to_bytes(&self) -> Cow<[u8]> {
         Cow::Owned(Encode!(self).unwrap())
@@ -61,21 +218,101 @@ impl Storable for Proposal {
 }
 
 // Implements traits for bounded storable data types
-impl BoundedStorable for Proposal {
-    const MAX_SIZE: u32 = MAX_VALUE_SIZE; // Maximum size
+impl BoundedStorable for Event {
+    const MAX_SIZE: u32 = MAX_EVENT_SIZE; // Maximum size
     const IS_FIXED_SIZE: bool = false; // Whether it's a fixed size or not
 }
 
-// Thread-local memory manager and Proposal map are defined
+// Implements traits for storable data types
+impl Storable for GovernanceConfig {
+    // Function to convert data to bytes
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+
+    // Function to convert bytes to data
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(bytes.as_ref(), Self).unwrap()
+    }
+}
+
+// Implements traits for bounded storable data types
+impl BoundedStorable for GovernanceConfig {
+    const MAX_SIZE: u32 = MAX_GOVERNANCE_CONFIG_SIZE; // Maximum size
+    const IS_FIXED_SIZE: bool = false; // Whether it's a fixed size or not
+}
+
+// Thread-local memory manager, Proposal map and voting-rights map are defined
 thread_local! {
     static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> = RefCell::new(MemoryManager::init(DefaultMemoryImpl::default()));
-    static PROPOSAL_MAP: RefCell<StableBTreeMap<u64, Proposal, Memory>> = RefCell::new(StableBTreeMap::init(MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(0)))));
+    static PROPOSAL_MAP: RefCell<StableBTreeMap<u64, VersionedProposal, Memory>> = RefCell::new(StableBTreeMap::init(MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(0)))));
+    static VOTING_RIGHTS_MAP: RefCell<StableBTreeMap<candid::Principal, u32, Memory>> = RefCell::new(StableBTreeMap::init(MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(1)))));
+    static DELEGATION_MAP: RefCell<StableBTreeMap<candid::Principal, candid::Principal, Memory>> = RefCell::new(StableBTreeMap::init(MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(2)))));
+    static WEIGHT_SNAPSHOT_MAP: RefCell<StableBTreeMap<(u64, candid::Principal), u32, Memory>> = RefCell::new(StableBTreeMap::init(MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(3)))));
+    static EVENT_MAP: RefCell<StableBTreeMap<u64, Event, Memory>> = RefCell::new(StableBTreeMap::init(MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(4)))));
+    static GOVERNANCE_CONFIG_MAP: RefCell<StableBTreeMap<u8, GovernanceConfig, Memory>> = RefCell::new(StableBTreeMap::init(MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(5)))));
+    static ADMIN_MAP: RefCell<StableBTreeMap<u8, candid::Principal, Memory>> = RefCell::new(StableBTreeMap::init(MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(6)))));
+    static SNAPSHOT_TAKEN_MAP: RefCell<StableBTreeMap<u64, bool, Memory>> = RefCell::new(StableBTreeMap::init(MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(7)))));
+}
+
+// Function defined to read the governance configuration, falling back to its default when unset
+fn governance_config() -> GovernanceConfig {
+    GOVERNANCE_CONFIG_MAP
+        .with(|c| c.borrow().get(&SINGLETON_KEY))
+        .unwrap_or_default()
+}
+
+// Function defined to read the current governance configuration
+#[ic_cdk::query]
+fn get_governance_config() -> GovernanceConfig {
+    governance_config()
+}
+
+// Function defined to set the governance configuration; the first caller becomes the admin
+#[ic_cdk::update]
+fn set_governance_config(config: GovernanceConfig) -> Result<(), VoteError> {
+    let caller = ic_cdk::caller();
+    let admin = ADMIN_MAP.with(|a| a.borrow().get(&SINGLETON_KEY));
+    if let Some(admin) = admin {
+        if admin != caller {
+            return Err(VoteError::AccessRejected);
+        }
+    }
+    ADMIN_MAP.with(|a| a.borrow_mut().insert(SINGLETON_KEY, caller));
+    GOVERNANCE_CONFIG_MAP.with(|c| c.borrow_mut().insert(SINGLETON_KEY, config));
+    Ok(())
+}
+
+// Function defined to append an Event to the audit trail under the next sequence number. The
+// sequence is derived from EVENT_MAP's own length rather than a separate counter, since the map
+// already lives in stable memory and survives an upgrade without any restore step
+fn record_event(event: Event) {
+    EVENT_MAP.with(|e| {
+        let mut map = e.borrow_mut();
+        let seq = map.len();
+        map.insert(seq, event);
+    });
+}
+
+// Function defined to page through the governance event log
+#[ic_cdk::query]
+fn get_events(from_seq: u64, limit: u64) -> Vec<Event> {
+    EVENT_MAP.with(|e| {
+        e.borrow()
+            .iter()
+            .filter(|(seq, _)| *seq >= from_seq)
+            .take(limit as usize)
+            .map(|(_, event)| event)
+            .collect()
+    })
 }
 
 // Function defined to get a Proposal
 #[ic_cdk::query]
-fn get_proposal(key: u64) -> Option<Proposal> {
-    PROPOSAL_MAP.with(|p| p.borrow().get(&key))
+fn get_proposal(key: u64) -> Option<ProposalV2> {
+    PROPOSAL_MAP
+        .with(|p| p.borrow().get(&key))
+        .map(VersionedProposal::into_v2)
 }
 
 // Function defined to get the count of Proposals
@@ -86,16 +323,382 @@ fn get_proposal_count() -> u64 {
 
 // Function defined to create a new Proposal
 #[ic_cdk::update]
-fn create_proposal(key: u64, proposal: CreateProposal) -> Option<Proposal> {
+fn create_proposal(key: u64, proposal: CreateProposal) -> Result<Option<ProposalV2>, VoteError> {
+    if proposal.duration_ns < governance_config().min_duration_ns {
+        return Err(VoteError::DurationTooShort);
+    }
+
+    // Voting opens only after the grant period, giving the chairperson a window to call
+    // give_voting_right before the electorate is frozen for this Proposal
+    let start_time_ns = ic_cdk::api::time() + GRANT_PERIOD_NS;
     // Creates a new Proposal and adds it to the Proposal map
-    let value: Proposal = Proposal {
+    let value = ProposalV2 {
         description: proposal.description,
         approve: 0u32,
         reject: 0u32,
         pass: 0u32,
-        is_active: proposal.is_active,
+        state: ProposalState::Pending,
+        start_time_ns,
+        end_time_ns: start_time_ns + proposal.duration_ns,
+        extended: false,
         voted: vec![],
         owner: ic_cdk::caller(),
     };
-    PROPOSAL_MAP.with(|p| p.borrow_mut().insert(key, value))
+
+    record_event(Event::ProposalCreated {
+        key,
+        owner: value.owner,
+    });
+
+    // A Proposal re-created under a used key takes a fresh weight snapshot of its own
+    SNAPSHOT_TAKEN_MAP.with(|s| s.borrow_mut().remove(&key));
+
+    let previous = PROPOSAL_MAP.with(|p| p.borrow_mut().insert(key, VersionedProposal::V2(value)));
+    Ok(previous.map(VersionedProposal::into_v2))
+}
+
+// Function defined to derive the current leader of the tally, used to detect closing-period flips
+fn leading_choice(proposal: &ProposalV2) -> std::cmp::Ordering {
+    proposal.approve.cmp(&proposal.reject)
+}
+
+// Function defined to freeze the full VOTING_RIGHTS_MAP distribution into this Proposal's
+// snapshot the first time it is touched after the grant period ends, i.e. at its first ballot,
+// so that a right granted after that ballot cannot change its balance of power. A right granted
+// after voting opens but before the first ballot is still included in the snapshot. Idempotent
+// via SNAPSHOT_TAKEN_MAP, since vote() calls this on every ballot and the copy must happen once
+fn ensure_weights_snapshotted(key: u64) {
+    let already_taken = SNAPSHOT_TAKEN_MAP
+        .with(|s| s.borrow().get(&key))
+        .unwrap_or(false);
+    if already_taken {
+        return;
+    }
+    VOTING_RIGHTS_MAP.with(|v| {
+        for (voter, weight) in v.borrow().iter() {
+            WEIGHT_SNAPSHOT_MAP.with(|s| s.borrow_mut().insert((key, voter), weight));
+        }
+    });
+    SNAPSHOT_TAKEN_MAP.with(|s| s.borrow_mut().insert(key, true));
+}
+
+// Function defined to read a voter's frozen weight for a Proposal; a voter absent from the
+// snapshot was never granted a right before voting opened and so has weight 0
+fn snapshot_weight(key: u64, voter: candid::Principal) -> u32 {
+    WEIGHT_SNAPSHOT_MAP
+        .with(|s| s.borrow().get(&(key, voter)))
+        .unwrap_or(0)
+}
+
+// Function defined to derive the live lifecycle state of a Proposal from the current time and tally
+fn live_state(proposal: &ProposalV2) -> ProposalState {
+    if proposal.state == ProposalState::Executed {
+        return ProposalState::Executed;
+    }
+
+    let now = ic_cdk::api::time();
+    if now < proposal.start_time_ns {
+        return ProposalState::Pending;
+    }
+    if now < proposal.end_time_ns {
+        return ProposalState::Active;
+    }
+
+    // Voting window has closed; the outcome is derived from quorum and the approval threshold
+    let participating_weight = proposal.approve + proposal.reject + proposal.pass;
+    if participating_weight == 0 {
+        return ProposalState::Expired;
+    }
+
+    let config = governance_config();
+    if participating_weight < config.quorum_weight {
+        return ProposalState::Defeated;
+    }
+
+    // Compared cross-multiplied rather than with a truncating division, so that e.g.
+    // approve=50/reject=49 against a 50% threshold reads as 50.5% > 50% instead of rounding
+    // the 50.5% down to 50 and wrongly calling it a tie
+    let approve_share = proposal.approve as u64 * 100;
+    let threshold_share = config.approval_threshold_pct as u64 * participating_weight as u64;
+    if approve_share > threshold_share {
+        ProposalState::Succeeded
+    } else {
+        ProposalState::Defeated
+    }
+}
+
+// Function defined to query the live lifecycle state of a Proposal
+#[ic_cdk::query]
+fn get_state(key: u64) -> Option<ProposalState> {
+    PROPOSAL_MAP
+        .with(|p| p.borrow().get(&key))
+        .map(VersionedProposal::into_v2)
+        .map(|proposal| live_state(&proposal))
+}
+
+// Function defined to persist a closed Proposal's derived outcome and record it as a
+// StateChanged event. Callable by anyone (an off-chain indexer or keeper, say), since it only
+// materializes what live_state already derives read-only; it is the sole path by which a
+// Defeated, Succeeded or Expired outcome ever reaches the event log, since get_state is a query
+// and cannot persist anything. A no-op, returning the already-recorded outcome, once finalized
+#[ic_cdk::update]
+fn finalize_proposal(key: u64) -> Result<ProposalState, VoteError> {
+    PROPOSAL_MAP.with(|p| {
+        let mut proposal = p
+            .borrow()
+            .get(&key)
+            .ok_or(VoteError::NoSuchProposal)?
+            .into_v2();
+
+        // An outcome already recorded stands, even if the governance configuration changed since
+        if matches!(
+            proposal.state,
+            ProposalState::Defeated
+                | ProposalState::Succeeded
+                | ProposalState::Expired
+                | ProposalState::Executed
+        ) {
+            return Ok(proposal.state);
+        }
+
+        let outcome = live_state(&proposal);
+        if matches!(outcome, ProposalState::Pending | ProposalState::Active) {
+            return Err(VoteError::ProposalStillOpen);
+        }
+        if proposal.state == outcome {
+            return Ok(outcome);
+        }
+
+        proposal.state = outcome.clone();
+        p.borrow_mut().insert(key, VersionedProposal::V2(proposal));
+        record_event(Event::StateChanged {
+            key,
+            new_state: outcome.clone(),
+        });
+        Ok(outcome)
+    })
+}
+
+// Function defined to carry out a Succeeded Proposal's outcome, owner-only. Finalizes the
+// Succeeded outcome first if that has not already happened, so the audit trail always shows a
+// Succeeded entry before the Executed one that follows it
+#[ic_cdk::update]
+fn execute_proposal(key: u64) -> Result<(), VoteError> {
+    let owner = PROPOSAL_MAP
+        .with(|p| p.borrow().get(&key))
+        .ok_or(VoteError::NoSuchProposal)?
+        .into_v2()
+        .owner;
+    if owner != ic_cdk::caller() {
+        return Err(VoteError::AccessRejected);
+    }
+
+    if finalize_proposal(key)? != ProposalState::Succeeded {
+        return Err(VoteError::NotSucceeded);
+    }
+
+    PROPOSAL_MAP.with(|p| {
+        let mut proposal = p
+            .borrow()
+            .get(&key)
+            .ok_or(VoteError::NoSuchProposal)?
+            .into_v2();
+
+        proposal.state = ProposalState::Executed;
+        p.borrow_mut().insert(key, VersionedProposal::V2(proposal));
+
+        record_event(Event::StateChanged {
+            key,
+            new_state: ProposalState::Executed,
+        });
+
+        Ok(())
+    })
+}
+
+// Function defined to grant a voter a vote of weight 1, chairperson-only. Takes a proposal key
+// rather than the bare `voter` the original request specified, since authorization is per
+// proposal (only that proposal's owner may call this) even though VOTING_RIGHTS_MAP itself is a
+// single global registry, not scoped per proposal — an intentional tradeoff: any chairperson may
+// grant a principal weight that then carries over to every other proposal, and WEIGHT_SNAPSHOT_MAP
+// is what actually freezes that global weight per proposal at its first ballot after the grant period
+#[ic_cdk::update]
+fn give_voting_right(key: u64, voter: candid::Principal) -> Result<(), VoteError> {
+    PROPOSAL_MAP.with(|p| {
+        // Looks up the Proposal so only its owner (the chairperson) may grant rights
+        let proposal = p
+            .borrow()
+            .get(&key)
+            .ok_or(VoteError::NoSuchProposal)?
+            .into_v2();
+        if proposal.owner != ic_cdk::caller() {
+            return Err(VoteError::AccessRejected);
+        }
+        VOTING_RIGHTS_MAP.with(|v| v.borrow_mut().insert(voter, 1u32));
+        Ok(())
+    })
+}
+
+// Function defined to check whether a principal has already voted on any Proposal
+fn has_already_voted(principal: &candid::Principal) -> bool {
+    PROPOSAL_MAP.with(|p| {
+        p.borrow()
+            .iter()
+            .any(|(_, proposal)| proposal.into_v2().voted.contains(principal))
+    })
+}
+
+// Function defined to follow a delegation chain to its root, capped to avoid unbounded work
+fn resolve_delegation_root(principal: candid::Principal) -> candid::Principal {
+    let mut current = principal;
+    let mut seen = BTreeSet::new();
+    let mut depth = 0;
+    while depth < MAX_DELEGATION_DEPTH {
+        if !seen.insert(current) {
+            break; // Cycle guard; delegate() should already prevent this from happening
+        }
+        match DELEGATION_MAP.with(|d| d.borrow().get(&current)) {
+            Some(next) => current = next,
+            None => break,
+        }
+        depth += 1;
+    }
+    current
+}
+
+// Function defined to lend a caller's voting weight to another principal
+#[ic_cdk::update]
+fn delegate(to: candid::Principal) -> Result<(), VoteError> {
+    let caller = ic_cdk::caller();
+
+    if caller == to {
+        return Err(VoteError::InvalidDelegation);
+    }
+    if has_already_voted(&caller) {
+        return Err(VoteError::InvalidDelegation);
+    }
+
+    // Walks the prospective chain starting at `to`, rejecting it if it loops back to the caller
+    let mut current = to;
+    let mut depth = 0;
+    loop {
+        if current == caller {
+            return Err(VoteError::InvalidDelegation);
+        }
+        if depth >= MAX_DELEGATION_DEPTH {
+            return Err(VoteError::InvalidDelegation);
+        }
+        match DELEGATION_MAP.with(|d| d.borrow().get(&current)) {
+            Some(next) => current = next,
+            None => break,
+        }
+        depth += 1;
+    }
+
+    DELEGATION_MAP.with(|d| d.borrow_mut().insert(caller, to));
+    Ok(())
+}
+
+// Function defined to cast a weighted ballot on a Proposal
+#[ic_cdk::update]
+fn vote(key: u64, choice: Choice) -> Result<(), VoteError> {
+    PROPOSAL_MAP.with(|p| {
+        let mut proposal = p
+            .borrow()
+            .get(&key)
+            .ok_or(VoteError::NoSuchProposal)?
+            .into_v2();
+
+        let now = ic_cdk::api::time();
+        if now < proposal.start_time_ns || now >= proposal.end_time_ns {
+            return Err(VoteError::ProposalIsNotActive);
+        }
+
+        let caller = ic_cdk::caller();
+        if proposal.voted.contains(&caller) {
+            return Err(VoteError::AlreadyVoted);
+        }
+
+        // A caller who has lent their weight to a delegate cannot also cast it directly, which
+        // would otherwise double-count it once the delegate votes
+        if DELEGATION_MAP.with(|d| d.borrow().get(&caller)).is_some() {
+            return Err(VoteError::InvalidDelegation);
+        }
+
+        // The window check above guarantees the grant period has ended, so this is the earliest
+        // point at which the electorate can be frozen for this Proposal
+        ensure_weights_snapshotted(key);
+
+        // Everyone whose delegation chain resolves to the caller lends their weight
+        let delegators: Vec<candid::Principal> = DELEGATION_MAP.with(|d| {
+            d.borrow()
+                .iter()
+                .filter_map(|(delegator, _)| {
+                    if resolve_delegation_root(delegator) == caller {
+                        Some(delegator)
+                    } else {
+                        None
+                    }
+                })
+                .collect()
+        });
+
+        // Weight is charged from the snapshot ensure_weights_snapshotted froze above
+        let own_weight = snapshot_weight(key, caller);
+        let delegated_weight: u32 = delegators
+            .iter()
+            .map(|delegator| snapshot_weight(key, *delegator))
+            .sum();
+        let weight = own_weight + delegated_weight;
+        if weight == 0 {
+            return Err(VoteError::AccessRejected);
+        }
+
+        // Leader before the vote is cast, used to detect a closing-period flip
+        let leader_before = leading_choice(&proposal);
+
+        match choice {
+            Choice::Approve => proposal.approve += weight,
+            Choice::Reject => proposal.reject += weight,
+            Choice::Pass => proposal.pass += weight,
+        }
+        proposal.voted.push(caller);
+        // Delegators cannot double-vote directly once their weight has been cast on their behalf
+        proposal.voted.extend(delegators);
+
+        // A late vote that flips the leader extends the window once by the closing period
+        if !proposal.extended && now + CLOSING_PERIOD_NS >= proposal.end_time_ns {
+            if leading_choice(&proposal) != leader_before {
+                proposal.end_time_ns += CLOSING_PERIOD_NS;
+                proposal.extended = true;
+            }
+        }
+
+        p.borrow_mut().insert(key, VersionedProposal::V2(proposal));
+
+        record_event(Event::VoteCast {
+            key,
+            voter: caller,
+            choice,
+            weight,
+        });
+
+        Ok(())
+    })
+}
+
+// Function defined to migrate every stored Proposal to the latest version after an upgrade,
+// rewriting all entries in one pass
+#[ic_cdk::post_upgrade]
+fn post_upgrade() {
+    let keys: Vec<u64> = PROPOSAL_MAP.with(|p| p.borrow().iter().map(|(key, _)| key).collect());
+    for key in keys {
+        if let Some(versioned) = PROPOSAL_MAP.with(|p| p.borrow().get(&key)) {
+            // Re-inserting rewrites the stored bytes in the current VersionedProposal encoding
+            PROPOSAL_MAP.with(|p| {
+                p.borrow_mut()
+                    .insert(key, VersionedProposal::V2(versioned.into_v2()))
+            });
+        }
+    }
 }